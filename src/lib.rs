@@ -8,16 +8,18 @@ use std::fmt;
 /// Represents an item that we want to chose with a given weight. The
 /// weight provided can be arbitrary, needless to say larger means more
 /// likely. This value will be normalized in relation to the other weights
-/// when provided to the `Alias::from_weighted_tuples`.
+/// when provided to the `Alias::from_weighted_tuples`. The weight may be any
+/// type implementing `Weight` (the integer and floating point primitives),
+/// so fractional and signed scales can be used without pre-scaling by hand.
 #[derive(Debug)]
-pub struct WeightedTuple<T: Copy> {
-    weight: u64,
+pub struct WeightedTuple<W, T: Copy> {
+    weight: W,
     value: T,
 }
 
-impl<T: Copy> WeightedTuple<T> {
+impl<W, T: Copy> WeightedTuple<W, T> {
     /// Initializes a new and immutable `WeightedTuple`
-    pub fn new(weight: u64, value: T) -> WeightedTuple<T> {
+    pub fn new(weight: W, value: T) -> WeightedTuple<W, T> {
         WeightedTuple {
             weight: weight,
             value: value,
@@ -25,12 +27,136 @@ impl<T: Copy> WeightedTuple<T> {
     }
 }
 
+/// The set of weight types accepted by `Alias::from_weighted_tuples`. Each
+/// weight is folded into the exact `Fraction` arithmetic the alias table is
+/// built on, so the only operation a type needs to supply is a lossless
+/// conversion into a `Fraction`; the summation, multiplication by the item
+/// count and the comparisons against one are all performed on the resulting
+/// fractions. Implementations reject values that cannot describe a
+/// probability, namely negatives and NaN.
+pub trait Weight: Copy {
+    /// Converts this weight into an exact `Fraction`, returning an
+    /// `AliasCreationError` when the value is negative or not a number.
+    fn try_into_fraction(self) -> Result<Fraction, AliasCreationError>;
+}
+
+macro_rules! impl_weight_unsigned {
+    ($($t:ty),*) => {$(
+        impl Weight for $t {
+            fn try_into_fraction(self) -> Result<Fraction, AliasCreationError> {
+                Ok(Fraction::from(self))
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_weight_signed {
+    ($($t:ty),*) => {$(
+        impl Weight for $t {
+            fn try_into_fraction(self) -> Result<Fraction, AliasCreationError> {
+                if self < 0 {
+                    return Err(AliasCreationError {
+                        message: format!("weight must not be negative, but was {}", self),
+                    });
+                }
+                Ok(Fraction::from(self))
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_weight_float {
+    ($($t:ty),*) => {$(
+        impl Weight for $t {
+            fn try_into_fraction(self) -> Result<Fraction, AliasCreationError> {
+                if self.is_nan() {
+                    return Err(AliasCreationError {
+                        message: String::from("weight must not be NaN"),
+                    });
+                }
+                if self < 0.0 {
+                    return Err(AliasCreationError {
+                        message: format!("weight must not be negative, but was {}", self),
+                    });
+                }
+                Ok(Fraction::from(self))
+            }
+        }
+    )*};
+}
+
+impl_weight_unsigned!(u8, u16, u32, u64, usize);
+impl_weight_signed!(i8, i16, i32, i64, isize);
+impl_weight_float!(f32, f64);
+
 #[derive(Debug)]
 struct NormalizedWeightTuple<T> {
     fractional_weight: Fraction,
     value: T,
 }
 
+/// A finalized bucket of the alias table. The exact `Fraction` arithmetic is
+/// only needed while the worklist is being balanced; once a bucket is placed
+/// we collapse its probability down to an `f64` so that the sampling hot path
+/// is a single comparison with no rational allocation.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ProbabilityBucket<T> {
+    probability: f64,
+    value: T,
+}
+
+/// Collapses an exact `Fraction` into an `f64`, used both for the per-bucket
+/// coin bias and for the reservoir keys in `select_multiple`.
+fn fraction_to_f64(fraction: Fraction) -> f64 {
+    match (fraction.numer(), fraction.denom()) {
+        (Some(numerator), Some(denominator)) => *numerator as f64 / *denominator as f64,
+        // Only NaN and infinite fractions lack a numerator or denominator;
+        // they collapse to one.
+        _ => 1.0,
+    }
+}
+
+/// Draws the reservoir key `r^(1/w)` for a single weighted item. Negative and
+/// `NaN` weights cannot describe a valid key, so they are clamped to zero,
+/// which yields a key of zero and therefore loses to any positive weight.
+fn reservoir_key<W: Weight>(weight: W, rng: &mut impl rand::Rng) -> f64 {
+    let weight = weight.try_into_fraction().map(fraction_to_f64).unwrap_or(0.0);
+    if weight <= 0.0 {
+        0.0
+    } else {
+        rng.gen::<f64>().powf(1.0 / weight)
+    }
+}
+
+/// A single reservoir slot for `select_multiple`, ordered by its A-Res key so
+/// that a `BinaryHeap` wrapped in `Reverse` behaves as a size-`k` min-heap.
+#[derive(Debug)]
+struct ReservoirEntry<T> {
+    key: f64,
+    value: T,
+}
+
+impl<T> PartialEq for ReservoirEntry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<T> Eq for ReservoirEntry<T> {}
+
+impl<T> PartialOrd for ReservoirEntry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for ReservoirEntry<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key.total_cmp(&other.key)
+    }
+}
+
 /*
 https://www.keithschwarz.com/darts-dice-coins/
 
@@ -66,7 +192,7 @@ Generation:
 
 #[derive(Debug)]
 pub struct Alias<T: Copy> {
-    probabilities: Vec<NormalizedWeightTuple<T>>,
+    probabilities: Vec<ProbabilityBucket<T>>,
     aliases: Vec<T>,
 }
 
@@ -90,27 +216,45 @@ impl fmt::Display for AliasCreationError {
 }
 
 impl<T: Copy> Alias<T> {
-    pub fn from_weighted_tuples(
-        items: &[WeightedTuple<T>],
+    pub fn from_weighted_tuples<W: Weight>(
+        items: &[WeightedTuple<W, T>],
     ) -> Result<Alias<T>, AliasCreationError> {
         // We have to scale all the weights, which involves converting them
         // into fractions in reference to all the other weights for context.
-        let count = items.len() as u64;
+        let count = items.len();
         if count == 0 {
             return Err(AliasCreationError {
                 message: String::from("no weighted tuples were provided"),
             });
         }
-        let sum = items
+        // Convert every weight into an exact fraction up front so the worklist
+        // arithmetic stays lossless no matter what numeric type was provided.
+        let mut fractional_weights = Vec::with_capacity(count);
+        for item in items {
+            fractional_weights.push((item.weight.try_into_fraction()?, item.value));
+        }
+        let sum = fractional_weights
             .iter()
-            .map(|wt| wt.weight)
-            .fold(0, |total, next| total + next);
-        let normalized_weight_tuples = items.iter().map(|wt| NormalizedWeightTuple {
-            fractional_weight: Fraction::new(wt.weight * count, sum),
-            value: wt.value,
-        });
+            .map(|(weight, _)| *weight)
+            .fold(Fraction::from(0u64), |total, next| total + next);
+        // A zero total weight leaves nothing to normalize against and would
+        // divide every weight by zero, so reject it up front alongside the
+        // per-weight negative/NaN checks.
+        if sum == Fraction::from(0u64) {
+            return Err(AliasCreationError {
+                message: String::from("the total weight must be greater than zero"),
+            });
+        }
+        let count_fraction = Fraction::from(count as u64);
+        let normalized_weight_tuples =
+            fractional_weights
+                .into_iter()
+                .map(|(weight, value)| NormalizedWeightTuple {
+                    fractional_weight: weight * count_fraction / sum,
+                    value: value,
+                });
         // This will be our finallized results
-        let mut finalized_probabilities: Vec<NormalizedWeightTuple<T>> = Vec::new();
+        let mut finalized_probabilities: Vec<ProbabilityBucket<T>> = Vec::new();
         let mut finalized_aliases: Vec<T> = Vec::new();
         // Now we need to partition the large and small probabilities. The large aliases
         // are spread across multiple "buckets" increasing their odds of being selected.
@@ -128,7 +272,10 @@ impl<T: Copy> Alias<T> {
         while !small_items.is_empty() && !large_items.is_empty() {
             let current_small_item = small_items.pop().unwrap();
             let current_large_item = large_items.pop().unwrap();
-            finalized_probabilities.push(current_small_item);
+            finalized_probabilities.push(ProbabilityBucket {
+                probability: fraction_to_f64(current_small_item.fractional_weight),
+                value: current_small_item.value,
+            });
             finalized_aliases.push(current_large_item.value);
             let reduced_fraction =
                 current_large_item.fractional_weight + current_large_item.fractional_weight - one;
@@ -145,8 +292,8 @@ impl<T: Copy> Alias<T> {
 
         while !large_items.is_empty() {
             let current_large_item = large_items.pop().unwrap();
-            finalized_probabilities.push(NormalizedWeightTuple {
-                fractional_weight: one,
+            finalized_probabilities.push(ProbabilityBucket {
+                probability: fraction_to_f64(one),
                 value: current_large_item.value,
             });
         }
@@ -158,12 +305,121 @@ impl<T: Copy> Alias<T> {
     }
 
     pub fn select(&self, rng: &mut impl rand::Rng) -> T {
-        let random_values: (usize, f32) = rng.gen();
-        let probability_index = random_values.0 % &self.probabilities.len();
+        use rand::distributions::Distribution;
+        self.sample(rng)
+    }
+
+    /// Draws `amount` distinct items with probability proportional to their
+    /// weight, i.e. weighted sampling *without* replacement — something the
+    /// alias table, which only models independent draws, cannot express. It
+    /// runs the Efraimidis–Spirakis A-Res reservoir algorithm over the
+    /// provided tuples: each item `i` is given the key `u_i^(1/w_i)` for a
+    /// fresh uniform `u_i`, and the `amount` largest keys win, tracked with a
+    /// size-`amount` binary min-heap.
+    ///
+    /// When `amount >= n` every item is returned. Zero-weight items receive a
+    /// key of zero and are therefore only chosen once nothing heavier remains.
+    /// Unlike [`from_weighted_tuples`](Self::from_weighted_tuples), which
+    /// returns an error for them, negative and `NaN` weights are clamped to
+    /// zero here so the single-pass selection can never fail.
+    pub fn select_multiple<W: Weight>(
+        items: &[WeightedTuple<W, T>],
+        amount: usize,
+        rng: &mut impl rand::Rng,
+    ) -> Vec<T> {
+        use std::cmp::Reverse;
+        use std::collections::BinaryHeap;
+
+        if amount >= items.len() {
+            return items.iter().map(|item| item.value).collect();
+        }
+        // `Reverse` turns the max-heap into a min-heap so the root is always
+        // the weakest survivor, ready to be displaced by a larger key.
+        let mut reservoir: BinaryHeap<Reverse<ReservoirEntry<T>>> =
+            BinaryHeap::with_capacity(amount);
+        for item in items {
+            let key = reservoir_key(item.weight, rng);
+            if reservoir.len() < amount {
+                reservoir.push(Reverse(ReservoirEntry {
+                    key: key,
+                    value: item.value,
+                }));
+            } else if let Some(Reverse(weakest)) = reservoir.peek() {
+                if key > weakest.key {
+                    reservoir.pop();
+                    reservoir.push(Reverse(ReservoirEntry {
+                        key: key,
+                        value: item.value,
+                    }));
+                }
+            }
+        }
+        reservoir
+            .into_iter()
+            .map(|Reverse(entry)| entry.value)
+            .collect()
+    }
+
+    /// Picks a single item proportional to weight from a one-pass stream,
+    /// without ever materializing an alias table. This is the streaming
+    /// counterpart to [`from_weighted_tuples`](Self::from_weighted_tuples):
+    /// it runs the Efraimidis–Spirakis A-ExpJ variant of weighted reservoir
+    /// sampling with a reservoir of size one. Rather than drawing a key for
+    /// every item, it keeps the current winner together with its threshold
+    /// key and an exponential "jump" `ln(r) / ln(threshold)`; each item only
+    /// decrements the jump by its weight, and just when the jump is exhausted
+    /// does the item enter the reservoir — drawing a uniform in
+    /// `[threshold^w, 1)` whose `1/w`-th power becomes the new key (so the key
+    /// lies in `[threshold, 1)`), and a new jump. Memory stays O(1) and positive-weight
+    /// items consume far fewer RNG draws than one-per-item A-Res. Returns
+    /// `None` for an empty stream. As with
+    /// [`select_multiple`](Self::select_multiple), negative and `NaN` weights
+    /// are clamped to zero rather than reported as an error. A zero-weight item
+    /// after the first is always skipped; a zero-weight first item is returned
+    /// only when no positive-weight item follows it.
+    pub fn select_from_iter<W, I>(iter: I, rng: &mut impl rand::Rng) -> Option<T>
+    where
+        W: Weight,
+        I: IntoIterator<Item = WeightedTuple<W, T>>,
+    {
+        let mut iter = iter.into_iter();
+        // Seed the size-one reservoir with the first item of the stream.
+        let first = iter.next()?;
+        let mut winner = first.value;
+        let mut threshold = reservoir_key(first.weight, rng);
+        // The jump counts down the cumulative weight we may skip before the
+        // next item is allowed to displace the winner.
+        let mut jump = rng.gen::<f64>().ln() / threshold.ln();
+        for item in iter {
+            // Negative / NaN weights are clamped to zero and, having no jump
+            // contribution, can never enter the reservoir.
+            let weight = item.weight.try_into_fraction().map(fraction_to_f64).unwrap_or(0.0);
+            if weight <= 0.0 {
+                continue;
+            }
+            jump -= weight;
+            if jump <= 0.0 {
+                // This item crosses the jump: draw its key from the tail above
+                // the current threshold, install it as the winner, and pick the
+                // next jump against the new threshold.
+                let tail = threshold.powf(weight);
+                threshold = rng.gen_range(tail..1.0).powf(1.0 / weight);
+                winner = item.value;
+                jump = rng.gen::<f64>().ln() / threshold.ln();
+            }
+        }
+        Some(winner)
+    }
+}
+
+impl<T: Copy> rand::distributions::Distribution<T> for Alias<T> {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> T {
+        // Vose generation: roll a fair die to pick a bucket, then flip a
+        // single biased coin against the bucket's precomputed probability.
+        let probability_index = rng.gen_range(0..self.probabilities.len());
         let current_probability = &self.probabilities[probability_index];
 
-        let random_probability = Fraction::from(random_values.1);
-        if random_probability <= current_probability.fractional_weight {
+        if rng.gen::<f64>() < current_probability.probability {
             return current_probability.value;
         } else {
             return self.aliases[probability_index];
@@ -171,6 +427,100 @@ impl<T: Copy> Alias<T> {
     }
 }
 
+/// (De)serialization of a precomputed alias table. Construction is `O(n)` and
+/// relies on exact `Fraction` arithmetic, so large fixed distributions (loot
+/// tables, language models) are best built once and reloaded thereafter. Only
+/// the `probabilities` and `aliases` vectors are persisted; on load the table
+/// is revalidated so a truncated or length-mismatched blob fails loudly rather
+/// than panicking during sampling.
+#[cfg(feature = "serde")]
+mod serde_support {
+    use super::{Alias, AliasCreationError, ProbabilityBucket};
+    use serde::de::{DeserializeOwned, Error as _};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize)]
+    struct AliasDataRef<'a, T> {
+        probabilities: &'a Vec<ProbabilityBucket<T>>,
+        aliases: &'a Vec<T>,
+    }
+
+    #[derive(Deserialize)]
+    struct AliasData<T> {
+        probabilities: Vec<ProbabilityBucket<T>>,
+        aliases: Vec<T>,
+    }
+
+    /// Rebuilds an `Alias` from its persisted parts, enforcing the invariants
+    /// the Vose construction guarantees: at least one bucket, no more aliases
+    /// than buckets, and every bucket without an alias entry must be certain
+    /// (probability of one) so the sampler never indexes past `aliases`.
+    fn from_parts<T: Copy>(
+        probabilities: Vec<ProbabilityBucket<T>>,
+        aliases: Vec<T>,
+    ) -> Result<Alias<T>, AliasCreationError> {
+        if probabilities.is_empty() {
+            return Err(AliasCreationError {
+                message: String::from("alias table must contain at least one bucket"),
+            });
+        }
+        if aliases.len() > probabilities.len() {
+            return Err(AliasCreationError {
+                message: format!(
+                    "alias table has {} aliases but only {} buckets",
+                    aliases.len(),
+                    probabilities.len()
+                ),
+            });
+        }
+        // Every probability must be a finite coin bias in `[0, 1]`; a NaN or
+        // out-of-range value would silently corrupt the distribution or, worse,
+        // fall through the sampler's coin flip and index a missing alias.
+        for (index, bucket) in probabilities.iter().enumerate() {
+            if !(bucket.probability.is_finite() && (0.0..=1.0).contains(&bucket.probability)) {
+                return Err(AliasCreationError {
+                    message: format!(
+                        "alias table bucket {} has an invalid probability",
+                        index
+                    ),
+                });
+            }
+            // Buckets past the alias vector are only ever reached on a certain
+            // coin flip, so they must be exactly one to avoid an out-of-bounds
+            // alias lookup at sample time.
+            if index >= aliases.len() && bucket.probability != 1.0 {
+                return Err(AliasCreationError {
+                    message: format!(
+                        "alias table bucket {} lacks an alias but is not certain",
+                        index
+                    ),
+                });
+            }
+        }
+        Ok(Alias {
+            probabilities: probabilities,
+            aliases: aliases,
+        })
+    }
+
+    impl<T: Copy + Serialize> Serialize for Alias<T> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            AliasDataRef {
+                probabilities: &self.probabilities,
+                aliases: &self.aliases,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Copy + DeserializeOwned> Deserialize<'de> for Alias<T> {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let data = AliasData::<T>::deserialize(deserializer)?;
+            from_parts(data.probabilities, data.aliases).map_err(D::Error::custom)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,7 +530,7 @@ mod tests {
 
     #[test]
     fn tuples_must_be_present() {
-      let empty: &[WeightedTuple<&str>] = &[];
+      let empty: &[WeightedTuple<u64, &str>] = &[];
       let no_tuples_alias = Alias::from_weighted_tuples(empty);
       match no_tuples_alias {
         Ok(_) => panic!("an empty set of tuples should fail, but didn't"),
@@ -250,4 +600,88 @@ mod tests {
 
         assert_eq!(Fraction::new(1u64, 2u64), Fraction::new(2u64, 4u64));
     }
+
+    #[test]
+    fn fractional_weights_are_accepted() {
+      let alias_result = Alias::from_weighted_tuples(&[
+        WeightedTuple::new(0.25f64, "a"),
+        WeightedTuple::new(0.75f64, "b"),
+      ]);
+      if let Err(e) = alias_result {
+        panic!("An error occured, but should not have: {}", e);
+      }
+    }
+
+    #[test]
+    fn select_multiple_returns_requested_count_without_duplicates() {
+      let items = &[
+        WeightedTuple::new(1u64, "a"),
+        WeightedTuple::new(2u64, "b"),
+        WeightedTuple::new(3u64, "c"),
+        WeightedTuple::new(4u64, "d"),
+      ];
+      let mut rng = thread_rng();
+      let chosen = Alias::select_multiple(items, 2, &mut rng);
+      assert_eq!(chosen.len(), 2);
+      assert_ne!(chosen[0], chosen[1]);
+    }
+
+    #[test]
+    fn select_multiple_returns_everything_when_amount_exceeds_len() {
+      let items = &[
+        WeightedTuple::new(1u64, "a"),
+        WeightedTuple::new(2u64, "b"),
+      ];
+      let mut rng = thread_rng();
+      let mut chosen = Alias::select_multiple(items, 10, &mut rng);
+      chosen.sort();
+      assert_eq!(chosen, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn select_from_iter_is_none_for_empty_stream() {
+      let empty: Vec<WeightedTuple<u64, &str>> = vec![];
+      let mut rng = thread_rng();
+      assert_eq!(Alias::select_from_iter(empty, &mut rng), None);
+    }
+
+    #[test]
+    fn select_from_iter_picks_a_present_item() {
+      let items = vec![
+        WeightedTuple::new(1u64, "a"),
+        WeightedTuple::new(2u64, "b"),
+        WeightedTuple::new(3u64, "c"),
+      ];
+      let mut rng = thread_rng();
+      let chosen = Alias::select_from_iter(items, &mut rng);
+      assert!(matches!(chosen, Some("a") | Some("b") | Some("c")));
+    }
+
+    #[test]
+    fn zero_total_weight_is_rejected() {
+      let alias_result = Alias::from_weighted_tuples(&[
+        WeightedTuple::new(0.0f64, "a"),
+        WeightedTuple::new(0.0f64, "b"),
+      ]);
+      match alias_result {
+        Ok(_) => panic!("a zero total weight should fail, but didn't"),
+        Err(e) => {
+          assert_eq!(format!("{}", e), "the total weight must be greater than zero");
+        },
+      }
+    }
+
+    #[test]
+    fn negative_weights_are_rejected() {
+      let alias_result = Alias::from_weighted_tuples(&[
+        WeightedTuple::new(-1i32, "a"),
+        WeightedTuple::new(2i32, "b"),
+      ]);
+      match alias_result {
+        Ok(_) => panic!("a negative weight should fail, but didn't"),
+        Err(e) => {
+          assert_eq!(format!("{}", e), "weight must not be negative, but was -1");
+        },
+      }
+    }
 }